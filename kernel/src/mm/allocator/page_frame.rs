@@ -1,4 +1,11 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt::Debug;
 use core::intrinsics::unlikely;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use bitflags::bitflags;
+use spin::Mutex;
 
 use crate::{
     arch::{mm::frame::LockedFrameAllocator, MMArch},
@@ -58,7 +65,8 @@ impl Iterator for PhysPageFrameIter {
         if unlikely(self.current == self.end) {
             return None;
         }
-        let current = self.current.next();
+        let current = self.current;
+        self.current = self.current.next();
         return Some(current);
     }
 }
@@ -116,7 +124,8 @@ impl Iterator for VirtPageFrameIter {
         if unlikely(self.current == self.end) {
             return None;
         }
-        let current: VirtPageFrame = self.current.next();
+        let current: VirtPageFrame = self.current;
+        self.current = self.current.next();
         return Some(current);
     }
 }
@@ -135,6 +144,23 @@ impl PageFrameCount {
     pub fn data(&self) -> usize {
         return self.0;
     }
+
+    /// @brief 将当前页帧数量向上取整到最近的2的幂次
+    ///
+    /// `allocate_aligned`的对齐参数是以2为底的指数，对应的值为结果的`data().trailing_zeros()`
+    pub fn next_power_of_two(&self) -> Self {
+        return Self(self.0.next_power_of_two());
+    }
+
+    /// @brief 2MiB大页所占的页帧数量
+    pub fn new_2m() -> Self {
+        return Self((2 * 1024 * 1024) / MMArch::PAGE_SIZE);
+    }
+
+    /// @brief 1GiB大页所占的页帧数量
+    pub fn new_1g() -> Self {
+        return Self((1024 * 1024 * 1024) / MMArch::PAGE_SIZE);
+    }
 }
 
 // 页帧使用情况
@@ -142,6 +168,11 @@ impl PageFrameCount {
 pub struct PageFrameUsage {
     used: PageFrameCount,
     total: PageFrameCount,
+    /// 自启动以来，被`allocate_page_frames_zeroed`清零过的页帧数量
+    zeroed: usize,
+    /// 自启动以来，在释放时被清除过内容的页帧数量（包括`deallocate_page_frames_scrubbed`，
+    /// 以及开启`SCRUB_ON_FREE`后经由`deallocate_page_frames`清除的）
+    scrubbed: usize,
 }
 
 impl PageFrameUsage {
@@ -149,7 +180,12 @@ impl PageFrameUsage {
     /// @param PageFrameCount used 已使用的页帧数量
     /// @param PageFrameCount total 总的页帧数量
     pub fn new(used: PageFrameCount, total: PageFrameCount) -> Self {
-        return Self { used, total };
+        return Self {
+            used,
+            total,
+            zeroed: FRAMES_ZEROED.load(Ordering::Relaxed),
+            scrubbed: FRAMES_SCRUBBED.load(Ordering::Relaxed),
+        };
     }
     // @brief 获取已使用的页帧数量
     pub fn used(&self) -> PageFrameCount {
@@ -163,6 +199,14 @@ impl PageFrameUsage {
     pub fn total(&self) -> PageFrameCount {
         return self.total;
     }
+    /// @brief 获取自启动以来，被清零过的页帧数量
+    pub fn zeroed(&self) -> usize {
+        return self.zeroed;
+    }
+    /// @brief 获取自启动以来，在释放时被清除过内容的页帧数量
+    pub fn scrubbed(&self) -> usize {
+        return self.scrubbed;
+    }
 }
 
 /// 能够分配页帧的分配器需要实现的trait
@@ -182,6 +226,54 @@ pub trait FrameAllocator {
     }
     // @brief 获取页帧使用情况
     unsafe fn usage(&self) -> PageFrameUsage;
+
+    /// @brief 分配count个页帧，且返回的物理地址按照2^align_log2个页帧对齐
+    ///
+    /// 默认实现通过“过量分配、再裁剪两端”的方式实现：先分配一段足够大、一定能包含对齐子区间的范围，
+    /// 再把对齐子区间前后多余的部分归还。这对本身不支持按对齐方式分配的分配器（如`LockedFrameAllocator`）
+    /// 也能生效，但会比原生支持对齐分配的分配器更浪费物理内存；有条件的分配器应当重写这个方法。
+    ///
+    /// @param count 请求分配的页帧数量
+    /// @param align_log2 返回的物理地址，按照2^align_log2个页帧对齐
+    unsafe fn allocate_aligned(
+        &mut self,
+        count: PageFrameCount,
+        align_log2: usize,
+    ) -> Option<PhysAddr> {
+        let align = 1usize << align_log2;
+        if align <= 1 {
+            return self.allocate(count);
+        }
+
+        // 多分配align-1个页帧，使得无论起始页号是多少，分配到的区间内一定包含一段对齐的子区间
+        let total = PageFrameCount::new(count.data() + align - 1);
+        let base = self.allocate(total)?;
+        let base_number = PhysPageFrame::new(base).number;
+        let aligned_number = (base_number + align - 1) & !(align - 1);
+
+        // 归还对齐子区间之前多分配的部分
+        let front = aligned_number - base_number;
+        if front > 0 {
+            self.free(base, PageFrameCount::new(front));
+        }
+
+        // 归还对齐子区间之后多分配的部分
+        let back = total.data() - front - count.data();
+        if back > 0 {
+            let tail = PhysPageFrame {
+                number: aligned_number + count.data(),
+            }
+            .phys_address();
+            self.free(tail, PageFrameCount::new(back));
+        }
+
+        return Some(
+            PhysPageFrame {
+                number: aligned_number,
+            }
+            .phys_address(),
+        );
+    }
 }
 
 /// @brief 通过一个 &mut T 的引用来对一个实现了 FrameAllocator trait 的类型进行调用，使代码更加灵活
@@ -201,6 +293,114 @@ impl<T: FrameAllocator> FrameAllocator for &mut T {
     unsafe fn usage(&self) -> PageFrameUsage {
         return T::usage(self);
     }
+    unsafe fn allocate_aligned(&mut self, count: PageFrameCount, align_log2: usize) -> Option<PhysAddr> {
+        return T::allocate_aligned(self, count, align_log2);
+    }
+}
+
+/// @brief 栈式页帧分配器
+///
+/// 适用于单页、同尺寸页帧反复申请/归还的场景（例如各类对象池）：从未被分配过的页帧
+/// 用`[current, end)`这一半开区间表示，`current`之前曾经被归还过的页帧记录在`recycled`中，
+/// 分配时优先从`recycled`中取出，这样可以避免在“伙伴系统”等通用分配器上产生不必要的开销。
+#[derive(Debug)]
+pub struct StackFrameAllocator {
+    /// 当前分配器所管理的页帧范围的起始页号
+    start: usize,
+    /// 从未被分配过的页帧中，第一个页帧的页号
+    current: usize,
+    /// 可分配的页帧页号范围的结束（不包含）
+    end: usize,
+    /// 已经被归还、可以重新被分配的页帧页号
+    recycled: Vec<usize>,
+}
+
+impl StackFrameAllocator {
+    pub fn new() -> Self {
+        return Self {
+            start: 0,
+            current: 0,
+            end: 0,
+            recycled: Vec::new(),
+        };
+    }
+
+    /// @brief 使用一段物理页帧区间，初始化当前分配器
+    ///
+    /// @param range 这段区间内的所有页帧，在初始化之后，都处于“从未被分配过”的状态
+    pub fn init(&mut self, range: PhysPageFrameIter) {
+        self.start = range.current.number;
+        self.current = range.current.number;
+        self.end = range.end.number;
+        self.recycled.clear();
+    }
+}
+
+impl FrameAllocator for StackFrameAllocator {
+    unsafe fn allocate(&mut self, count: PageFrameCount) -> Option<PhysAddr> {
+        let count = count.data();
+        if count == 1 {
+            return self.allocate_one();
+        }
+
+        // 多页分配要求是一段连续区间，而`recycled`中的页帧是零散的，因此只能从未分配过的区间中取
+        if self.current + count <= self.end {
+            let start = self.current;
+            self.current += count;
+            return Some(PhysPageFrame { number: start }.phys_address());
+        } else {
+            return None;
+        }
+    }
+
+    unsafe fn free(&mut self, address: PhysAddr, count: PageFrameCount) {
+        let start = PhysPageFrame::new(address).number;
+        for number in start..start + count.data() {
+            self.free_page(number);
+        }
+    }
+
+    unsafe fn allocate_one(&mut self) -> Option<PhysAddr> {
+        let number = if let Some(number) = self.recycled.pop() {
+            number
+        } else if self.current == self.end {
+            return None;
+        } else {
+            let number = self.current;
+            self.current += 1;
+            number
+        };
+
+        return Some(PhysPageFrame { number }.phys_address());
+    }
+
+    unsafe fn free_one(&mut self, address: PhysAddr) {
+        let number = PhysPageFrame::new(address).number;
+        self.free_page(number);
+    }
+
+    unsafe fn usage(&self) -> PageFrameUsage {
+        let total = PageFrameCount::new(self.end - self.start);
+        let used = PageFrameCount::new((self.current - self.start) - self.recycled.len());
+        return PageFrameUsage::new(used, total);
+    }
+}
+
+impl StackFrameAllocator {
+    /// @brief 归还一个页帧
+    ///
+    /// 在debug模式下，会检查这个页帧是否在已分配的范围内、以及是否被重复归还，以便及早发现重复释放的bug。
+    fn free_page(&mut self, number: usize) {
+        debug_assert!(
+            number < self.current,
+            "Frame {number} has never been allocated by this allocator"
+        );
+        debug_assert!(
+            !self.recycled.contains(&number),
+            "Frame {number} has already been freed (double free)"
+        );
+        self.recycled.push(number);
+    }
 }
 
 /// @brief 从全局的页帧分配器中分配连续count个页帧
@@ -215,12 +415,499 @@ pub fn allocate_page_frames(count: PageFrameCount) -> Option<PhysPageFrame> {
     return Some(frame);
 }
 
+/// @brief 从全局的页帧分配器中分配连续count个页帧，且返回的物理地址按照2^align_log2个页帧对齐
+///
+/// 用于设备DMA、大页等要求物理地址对齐到特定边界的场景。
+///
+/// @param count 请求分配的页帧数量
+/// @param align_log2 返回的物理地址，按照2^align_log2个页帧对齐
+pub fn allocate_page_frames_aligned(
+    count: PageFrameCount,
+    align_log2: usize,
+) -> Option<PhysPageFrame> {
+    let frame = unsafe {
+        LockedFrameAllocator
+            .allocate_aligned(count, align_log2)
+            .map(|addr| PhysPageFrame::new(addr))?
+    };
+    return Some(frame);
+}
+
 /// @brief 向全局页帧分配器释放连续count个页帧
 ///
 /// @param frame 要释放的第一个页帧
 /// @param count 要释放的页帧数量
 pub fn deallocate_page_frames(frame: PhysPageFrame, count: PageFrameCount) {
+    if scrub_on_free_enabled() {
+        write_zeros(frame, count);
+        FRAMES_SCRUBBED.fetch_add(count.data(), Ordering::Relaxed);
+    }
     unsafe {
         LockedFrameAllocator.free(frame.phys_address(), count);
     }
+}
+
+/// 是否在每次通过`deallocate_page_frames`释放页帧时，都清除其内容
+///
+/// 默认关闭（会带来额外的写内存开销）。对性能不敏感、但需要避免秘密数据残留的启动阶段，
+/// 可以通过`set_scrub_on_free`全局开启，使之后所有经由`deallocate_page_frames`释放的
+/// 页帧都会被清除内容，而不需要每个调用点都显式调用`deallocate_page_frames_scrubbed`。
+static SCRUB_ON_FREE: AtomicBool = AtomicBool::new(false);
+
+/// 自启动以来，被`allocate_page_frames_zeroed`清零过的页帧数量
+static FRAMES_ZEROED: AtomicUsize = AtomicUsize::new(0);
+/// 自启动以来，被清除过内容（清零）的页帧数量，既包括`deallocate_page_frames_scrubbed`
+/// 主动清除的，也包括`SCRUB_ON_FREE`开启后，由`deallocate_page_frames`清除的
+static FRAMES_SCRUBBED: AtomicUsize = AtomicUsize::new(0);
+
+/// @brief 全局地开启/关闭“释放页帧时清除其内容”
+pub fn set_scrub_on_free(enabled: bool) {
+    SCRUB_ON_FREE.store(enabled, Ordering::Relaxed);
+}
+
+/// @brief 查询当前是否已经全局开启了“释放页帧时清除其内容”
+pub fn scrub_on_free_enabled() -> bool {
+    return SCRUB_ON_FREE.load(Ordering::Relaxed);
+}
+
+/// @brief 将[frame, frame+count)这段页帧的内容清零
+///
+/// 通过arch提供的直接映射窗口（`MMArch::phys_2_virt`）访问物理内存
+fn write_zeros(frame: PhysPageFrame, count: PageFrameCount) {
+    for page in PhysPageFrame::iter_range(frame, frame.next_by(count.data())) {
+        unsafe {
+            let virt = MMArch::phys_2_virt(page.phys_address()).unwrap();
+            core::ptr::write_bytes(virt.data() as *mut u8, 0, MMArch::PAGE_SIZE);
+        }
+    }
+}
+
+/// @brief 从全局的页帧分配器中分配连续count个页帧，并在返回前将其内容清零
+///
+/// 用于将页帧交给用户态之前，避免内核残留数据泄漏给用户态。
+///
+/// @param count 请求分配的页帧数量
+pub fn allocate_page_frames_zeroed(count: PageFrameCount) -> Option<PhysPageFrame> {
+    let frame = allocate_page_frames(count)?;
+    write_zeros(frame, count);
+    FRAMES_ZEROED.fetch_add(count.data(), Ordering::Relaxed);
+    return Some(frame);
+}
+
+/// @brief 将[frame, frame+count)这段页帧的内容清除后，再归还给全局页帧分配器
+///
+/// 用于释放可能携带秘密数据（如加密密钥、用户凭据）的页帧，避免它们被重新分配后读取到。
+///
+/// @param frame 要释放的第一个页帧
+/// @param count 要释放的页帧数量
+pub fn deallocate_page_frames_scrubbed(frame: PhysPageFrame, count: PageFrameCount) {
+    // 如果全局已经开启了SCRUB_ON_FREE，deallocate_page_frames会重复清除一遍，这里就不用再清除了
+    if !scrub_on_free_enabled() {
+        write_zeros(frame, count);
+        FRAMES_SCRUBBED.fetch_add(count.data(), Ordering::Relaxed);
+    }
+    deallocate_page_frames(frame, count);
+}
+
+/// 记录被多个虚拟地址共享的单个物理页帧的引用计数
+///
+/// 只有被`frame_add_ref`显式记录过的页帧才会出现在这张表里；绝大多数只被唯一地址引用的
+/// 页帧完全不经过这张表，因此不会给常规的分配/释放路径带来额外开销。主要用于fork()产生的
+/// 写时复制（Copy-on-Write）页面：父子进程的页表项在复制前各自减少到只持有只读映射，
+/// 但都指向同一个物理页帧，直到其中一方尝试写入为止。
+static FRAME_REFCOUNT: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+/// @brief 增加一个物理页帧的引用计数
+///
+/// 如果这个页帧此前还不在共享状态（即没有被记录过），那么将其引用计数初始化为2
+/// （代表调用者和原持有者各占一份）。
+pub fn frame_add_ref(frame: PhysAddr) {
+    let number = PhysPageFrame::new(frame).number;
+    let mut table = FRAME_REFCOUNT.lock();
+    table
+        .entry(number)
+        .and_modify(|count| *count += 1)
+        .or_insert(2);
+}
+
+/// @brief 减少一个物理页帧的引用计数
+///
+/// 如果减少后引用计数仍然大于0，这个页帧不会被释放；如果引用计数降为0，或者这个页帧原本
+/// 就不处于共享状态（只有唯一持有者），那么就将其归还给全局页帧分配器。
+pub fn frame_drop_ref(frame: PhysAddr) {
+    let number = PhysPageFrame::new(frame).number;
+    let mut table = FRAME_REFCOUNT.lock();
+    match table.get_mut(&number) {
+        Some(count) if *count > 1 => {
+            *count -= 1;
+        }
+        Some(_) => {
+            table.remove(&number);
+            drop(table);
+            deallocate_page_frames(PhysPageFrame { number }, PageFrameCount::new(1));
+        }
+        None => {
+            drop(table);
+            deallocate_page_frames(PhysPageFrame { number }, PageFrameCount::new(1));
+        }
+    }
+}
+
+/// @brief 以RAII的方式持有一段连续的页帧，在Drop时自动将其归还给全局页帧分配器
+///
+/// 用于替代“手动allocate_page_frames + 手动deallocate_page_frames”的模式，
+/// 避免调用者忘记释放而导致物理内存泄漏。
+#[derive(Debug)]
+pub struct FrameTracker {
+    frame: PhysPageFrame,
+    count: PageFrameCount,
+}
+
+impl FrameTracker {
+    /// @brief 构造一个FrameTracker，持有[frame, frame+count)这段页帧的所有权
+    ///
+    /// 请注意，这段页帧应当是已经分配好的，本函数不会执行分配操作。
+    pub fn new(frame: PhysPageFrame, count: PageFrameCount) -> Self {
+        return Self { frame, count };
+    }
+
+    /// @brief 获取当前页帧段的起始物理地址
+    pub fn phys_address(&self) -> PhysAddr {
+        return self.frame.phys_address();
+    }
+
+    /// @brief 获取当前页帧段所占的页帧数量
+    pub fn count(&self) -> PageFrameCount {
+        return self.count;
+    }
+
+    /// @brief 获取当前页帧段中，所有物理页帧的迭代器
+    pub fn iter(&self) -> PhysPageFrameIter {
+        return PhysPageFrame::iter_range(self.frame, self.frame.next_by(self.count.data()));
+    }
+}
+
+impl Drop for FrameTracker {
+    fn drop(&mut self) {
+        deallocate_page_frames(self.frame, self.count);
+    }
+}
+
+/// @brief 从全局的页帧分配器中分配连续count个页帧，并以FrameTracker的形式返回
+///
+/// 相比于`allocate_page_frames`，调用者无需手动调用`deallocate_page_frames`，
+/// 返回的`FrameTracker`会在生命周期结束时自动释放所占有的页帧。
+///
+/// @param count 请求分配的页帧数量
+pub fn allocate_page_frames_tracked(count: PageFrameCount) -> Option<FrameTracker> {
+    let frame = allocate_page_frames(count)?;
+    return Some(FrameTracker::new(frame, count));
+}
+
+bitflags! {
+    /// @brief 内存区域的访问权限
+    pub struct MemoryAreaFlags: u8 {
+        const READ = 1 << 0;
+        const WRITE = 1 << 1;
+        const EXECUTE = 1 << 2;
+        const USER = 1 << 3;
+    }
+}
+
+/// @brief 内存区域的映射方式
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MemoryAreaMapType {
+    /// 恒等映射：虚拟页帧和物理页帧的页号相同（一般用于内核的线性映射区域），不持有FrameTracker
+    Identical,
+    /// 按需映射：每个虚拟页帧都对应着一个单独分配的物理页帧
+    Framed,
+}
+
+/// @brief 虚拟内存区域
+///
+/// 描述一段连续的虚拟页帧、它们的映射方式以及访问权限，是对“一段地址空间”的声明式表示，
+/// 避免上层代码（如地址空间的构建）手写零散的页帧分配/映射循环。
+#[derive(Debug)]
+pub struct MemoryArea {
+    /// 这段内存区域的第一个虚拟页帧
+    start: VirtPageFrame,
+    /// 这段内存区域的结束虚拟页帧（不包含）
+    end: VirtPageFrame,
+    map_type: MemoryAreaMapType,
+    flags: MemoryAreaFlags,
+    /// 对于`Framed`的内存区域，记录每个虚拟页帧所映射到的物理页帧
+    frames: BTreeMap<VirtPageFrame, FrameTracker>,
+}
+
+impl MemoryArea {
+    pub fn new(range: VirtPageFrameIter, map_type: MemoryAreaMapType, flags: MemoryAreaFlags) -> Self {
+        return Self {
+            start: range.current,
+            end: range.end,
+            map_type,
+            flags,
+            frames: BTreeMap::new(),
+        };
+    }
+
+    /// @brief 获取这段内存区域所包含的虚拟页帧的迭代器
+    pub fn iter(&self) -> VirtPageFrameIter {
+        return VirtPageFrame::iter_range(self.start, self.end);
+    }
+
+    pub fn map_type(&self) -> MemoryAreaMapType {
+        return self.map_type;
+    }
+
+    pub fn flags(&self) -> MemoryAreaFlags {
+        return self.flags;
+    }
+
+    /// @brief 为这段内存区域建立映射
+    ///
+    /// 对于`Identical`的内存区域，不需要分配物理页帧（调用者负责建立恒等映射的页表项）；
+    /// 对于`Framed`的内存区域，为区域内的每一个虚拟页帧分配一个物理页帧。
+    ///
+    /// @return 如果物理页帧不足导致分配失败，返回None（此时已经分配成功的页帧仍会在unmap/drop时被释放）
+    pub fn map(&mut self) -> Option<()> {
+        if self.map_type != MemoryAreaMapType::Framed {
+            return Some(());
+        }
+
+        for vpf in self.iter() {
+            let tracker = allocate_page_frames_tracked(PageFrameCount::new(1))?;
+            self.frames.insert(vpf, tracker);
+        }
+        return Some(());
+    }
+
+    /// @brief 取消这段内存区域的映射，释放它所持有的所有物理页帧
+    pub fn unmap(&mut self) {
+        self.frames.clear();
+    }
+
+    /// @brief 从offset开始，将data逐页拷贝到这段`Framed`内存区域所映射到的物理页帧中
+    ///
+    /// 要求调用者已经对这段区域调用过`map()`。
+    pub fn copy_data(&mut self, offset: usize, data: &[u8]) {
+        debug_assert_eq!(
+            self.map_type,
+            MemoryAreaMapType::Framed,
+            "Only framed memory areas own the physical frames backing them"
+        );
+
+        let page_size = MMArch::PAGE_SIZE;
+        let area_size = (self.end.number - self.start.number) * page_size;
+        // 这里必须是一个在release构建下也会生效的检查：调用方一旦传入越界的offset/data，
+        // 说明加载的镜像本身就超出了这段内存区域能容纳的范围，应当立即失败，而不是静默截断，
+        // 让调用方误以为数据已经完整拷贝进去
+        assert!(
+            offset + data.len() <= area_size,
+            "copy_data: [offset, offset + data.len()) exceeds the memory area's page range"
+        );
+        let mut page_offset = offset;
+        let mut remain = data;
+
+        for vpf in self.iter() {
+            if remain.is_empty() {
+                break;
+            }
+            if page_offset >= page_size {
+                page_offset -= page_size;
+                continue;
+            }
+
+            let tracker = self
+                .frames
+                .get(&vpf)
+                .expect("The frame of a framed memory area must be allocated before copy_data");
+            let len = core::cmp::min(page_size - page_offset, remain.len());
+            unsafe {
+                let dst = MMArch::phys_2_virt(tracker.phys_address())
+                    .unwrap()
+                    .data() as *mut u8;
+                core::ptr::copy_nonoverlapping(remain.as_ptr(), dst.add(page_offset), len);
+            }
+
+            remain = &remain[len..];
+            page_offset = 0;
+        }
+    }
+}
+
+/// SV39分页模式下，每一级页表所拥有的页表项数量
+const PAGE_TABLE_ENTRIES: usize = 512;
+/// SV39分页模式的级数
+const PAGE_TABLE_LEVELS: usize = 3;
+/// 页表项中，物理页号所在位域的起始位
+const PAGE_TABLE_ENTRY_PPN_SHIFT: usize = 10;
+
+bitflags! {
+    /// @brief 页表项中的标志位
+    pub struct PageTableFlags: u8 {
+        /// 该页表项是否有效
+        const VALID = 1 << 0;
+        const READABLE = 1 << 1;
+        const WRITABLE = 1 << 2;
+        const EXECUTABLE = 1 << 3;
+        /// 用户态是否可以访问
+        const USER = 1 << 4;
+        /// 自上一次被清除后，该页表项指向的页面是否被访问过
+        const ACCESSED = 1 << 5;
+        /// 自上一次被清除后，该页表项指向的页面是否被写过
+        const DIRTY = 1 << 6;
+    }
+}
+
+/// @brief 页表项：将一个`PhysPageFrame`的页号和一组`PageTableFlags`打包进一个`usize`中
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub struct PageTableEntry(usize);
+
+impl PageTableEntry {
+    fn new(frame: PhysPageFrame, flags: PageTableFlags) -> Self {
+        return Self((frame.number << PAGE_TABLE_ENTRY_PPN_SHIFT) | flags.bits() as usize);
+    }
+
+    /// @brief 获取当前页表项指向的物理页帧
+    pub fn frame(&self) -> PhysPageFrame {
+        return PhysPageFrame {
+            number: self.0 >> PAGE_TABLE_ENTRY_PPN_SHIFT,
+        };
+    }
+
+    /// @brief 获取当前页表项的标志位
+    pub fn flags(&self) -> PageTableFlags {
+        return PageTableFlags::from_bits_truncate(self.0 as u8);
+    }
+
+    /// @brief 当前页表项是否有效
+    pub fn is_valid(&self) -> bool {
+        return self.flags().contains(PageTableFlags::VALID);
+    }
+}
+
+impl Debug for PageTableEntry {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PageTableEntry")
+            .field("frame", &self.frame())
+            .field("flags", &self.flags())
+            .finish()
+    }
+}
+
+/// @brief 多级页表：以`PhysPageFrame`/`VirtPageFrame`直接表达SV39的三级分页结构
+///
+/// 与`super::super::page::PageTable`（基于`MemoryManagementArch`的、与具体架构无关的页表抽象）不同，
+/// 这是一个直接针对SV39的、轻量级的实现，主要用于只需要按页帧粒度进行映射/查询的场景。
+pub struct PageTable {
+    /// 根页表所在的页帧
+    root: FrameTracker,
+    /// 当前页表所分配的所有中间级页表，用于保证它们和根页表拥有相同的生命周期
+    frames: Vec<FrameTracker>,
+}
+
+impl PageTable {
+    /// @brief 创建一个新的、空的多级页表
+    pub fn new() -> Option<Self> {
+        let root = allocate_page_frames_tracked(PageFrameCount::new(1))?;
+        unsafe {
+            for pte in Self::table_of(PhysPageFrame::new(root.phys_address())).iter_mut() {
+                *pte = PageTableEntry::new(PhysPageFrame { number: 0 }, PageTableFlags::empty());
+            }
+        }
+        return Some(Self {
+            root,
+            frames: Vec::new(),
+        });
+    }
+
+    /// @brief 将虚拟页帧vpn映射到物理页帧ppn，权限为flags
+    ///
+    /// @return 如果分配中间级页表失败，返回None；如果vpn已经被映射过，则panic
+    pub fn map(&mut self, vpn: VirtPageFrame, ppn: PhysPageFrame, flags: PageTableFlags) -> Option<()> {
+        let pte = self.find_pte_create(vpn)?;
+        assert!(
+            !pte.is_valid(),
+            "Virtual page frame {:?} is already mapped",
+            vpn.virt_address()
+        );
+        *pte = PageTableEntry::new(ppn, flags | PageTableFlags::VALID);
+        return Some(());
+    }
+
+    /// @brief 查询虚拟页帧vpn所映射到的物理页帧及其标志位
+    pub fn translate(&self, vpn: VirtPageFrame) -> Option<(PhysPageFrame, PageTableFlags)> {
+        let pte = self.find_pte(vpn)?;
+        if pte.is_valid() {
+            return Some((pte.frame(), pte.flags()));
+        } else {
+            return None;
+        }
+    }
+
+    /// @brief 将vpn的页号按level数切分成SV39三级页表的下标
+    fn indexes(vpn: VirtPageFrame) -> [usize; PAGE_TABLE_LEVELS] {
+        let number = vpn.number;
+        return [
+            (number >> 18) & (PAGE_TABLE_ENTRIES - 1),
+            (number >> 9) & (PAGE_TABLE_ENTRIES - 1),
+            number & (PAGE_TABLE_ENTRIES - 1),
+        ];
+    }
+
+    /// @brief 获取位于frame这个页帧上的页表，以页表项数组的形式返回
+    ///
+    /// 页表总是通过direct-map/线性映射窗口访问的，因此这里返回的引用的生命周期与frame本身绑定，
+    /// 而不是与任何局部借用绑定。
+    unsafe fn table_of(frame: PhysPageFrame) -> &'static mut [PageTableEntry; PAGE_TABLE_ENTRIES] {
+        let virt = MMArch::phys_2_virt(frame.phys_address()).unwrap();
+        return &mut *(virt.data() as *mut [PageTableEntry; PAGE_TABLE_ENTRIES]);
+    }
+
+    /// @brief 查找vpn对应的页表项，如果经过的中间级页表不存在，就分配它
+    fn find_pte_create(&mut self, vpn: VirtPageFrame) -> Option<&'static mut PageTableEntry> {
+        let indexes = Self::indexes(vpn);
+        let mut ppn = PhysPageFrame::new(self.root.phys_address());
+
+        for (level, &index) in indexes.iter().enumerate() {
+            let pte = unsafe { &mut Self::table_of(ppn)[index] };
+            if level == PAGE_TABLE_LEVELS - 1 {
+                return Some(pte);
+            }
+
+            if !pte.is_valid() {
+                let table = allocate_page_frames_tracked(PageFrameCount::new(1))?;
+                let table_ppn = PhysPageFrame::new(table.phys_address());
+                unsafe {
+                    for new_pte in Self::table_of(table_ppn).iter_mut() {
+                        *new_pte = PageTableEntry::new(PhysPageFrame { number: 0 }, PageTableFlags::empty());
+                    }
+                }
+                // 指向下一级页表的页表项，只需要置位VALID，R/W/X均为0
+                *pte = PageTableEntry::new(table_ppn, PageTableFlags::VALID);
+                self.frames.push(table);
+            }
+            ppn = pte.frame();
+        }
+        unreachable!();
+    }
+
+    /// @brief 查找vpn对应的页表项，如果经过的中间级页表不存在，返回None
+    fn find_pte(&self, vpn: VirtPageFrame) -> Option<&'static PageTableEntry> {
+        let indexes = Self::indexes(vpn);
+        let mut ppn = PhysPageFrame::new(self.root.phys_address());
+
+        for (level, &index) in indexes.iter().enumerate() {
+            let pte = unsafe { &Self::table_of(ppn)[index] };
+            if level == PAGE_TABLE_LEVELS - 1 {
+                return Some(pte);
+            }
+            if !pte.is_valid() {
+                return None;
+            }
+            ppn = pte.frame();
+        }
+        unreachable!();
+    }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+use alloc::vec::Vec;
 use core::{
     fmt::{self, Debug},
     marker::PhantomData,
@@ -8,8 +9,8 @@ use core::{
 use crate::{arch::MMArch, kerror};
 
 use super::{
-    allocator::page_frame::FrameAllocator, MemoryManagementArch, PageTableKind, PhysAddr,
-    PhysMemoryArea, VirtAddr,
+    allocator::page_frame::{frame_add_ref, frame_drop_ref, FrameAllocator, PageFrameCount},
+    MemoryManagementArch, PageTableKind, PhysAddr, PhysMemoryArea, VirtAddr,
 };
 
 pub struct PageTable<Arch> {
@@ -131,6 +132,16 @@ impl<Arch: MemoryManagementArch> PageTable<Arch> {
         return Some(index & Arch::PAGE_ENTRY_MASK);
     }
 
+    /// @brief 将当前页表所在的物理页清零，使其所有页表项都处于“未填写”的状态
+    ///
+    /// 新分配的页表页帧可能残留上一次使用时的数据，在把它链接进页表树之前必须先清零，
+    /// 否则其中碰巧形似合法页表项的残留数据会在被遍历时造成指向随机物理内存的“幽灵”映射。
+    pub unsafe fn zero(&self) {
+        for i in 0..Arch::PAGE_ENTRY_NUM {
+            self.set_entry(i, PageEntry::new(0));
+        }
+    }
+
     /// @brief 获取第i个页表项指向的下一级页表
     pub unsafe fn next_level_table(&self, index: usize) -> Option<Self> {
         if self.level == 0 {
@@ -196,6 +207,29 @@ impl<Arch: MemoryManagementArch> PageEntry<Arch> {
     pub fn present(&self) -> bool {
         return self.data & Arch::ENTRY_FLAG_PRESENT != 0;
     }
+
+    /// @brief 判断当前页表项，在给定的level下，是否是一个大页（huge page）的叶子节点
+    ///
+    /// 在非0级的页表中，一个页表项要么指向下一级页表，要么（在支持大页的情况下）直接指向
+    /// 一个更大的物理页面。我们用该页表项是否携带了R/W/X这几个标志位中的任意一个来区分这两种情况：
+    /// 单纯指向下一级页表的页表项，只会有`ENTRY_FLAG_DEFAULT_TABLE`等“指针”类标志位。
+    /// 第0级页表的页表项永远是4KiB的普通叶子节点，不属于大页，因此这里需要显式传入level，
+    /// 避免调用方误把level 0的普通叶子项当成大页。
+    #[inline(always)]
+    pub fn is_huge(&self, level: usize) -> bool {
+        return level != 0
+            && self.data
+                & (Arch::ENTRY_FLAG_READWRITE | Arch::ENTRY_FLAG_READONLY | Arch::ENTRY_FLAG_EXEC)
+                != 0;
+    }
+
+    /// @brief 判断当前页表项，在给定的level下，是否是一个叶子节点
+    ///
+    /// 第0级页表的页表项总是叶子节点；其它level下，只有大页才是叶子节点
+    #[inline(always)]
+    pub fn is_leaf(&self, level: usize) -> bool {
+        return level == 0 || self.is_huge(level);
+    }
 }
 
 /// 页表项的标志位
@@ -321,6 +355,77 @@ impl<Arch: MemoryManagementArch> PageFlags<Arch> {
         return self.data & (Arch::ENTRY_FLAG_EXEC | Arch::ENTRY_FLAG_NO_EXEC)
             == Arch::ENTRY_FLAG_EXEC;
     }
+
+    /// @brief 设置当前页表项的Accessed位
+    ///
+    /// Accessed位由MMU在该页表项第一次被访问（无论读写）时自动置位，软件可以在回收内存时
+    /// 读取并清除它，以构建“最近是否被访问过”的信息
+    #[must_use]
+    #[inline(always)]
+    pub fn set_accessed(self, value: bool) -> Self {
+        return self.update_flags(Arch::ENTRY_FLAG_ACCESSED, value);
+    }
+
+    /// @brief 当前页表项自上次被清除Accessed位以来，是否被访问过
+    #[inline(always)]
+    pub fn accessed(&self) -> bool {
+        return self.has_flag(Arch::ENTRY_FLAG_ACCESSED);
+    }
+
+    /// @brief 清除当前页表项的Accessed位
+    #[must_use]
+    #[inline(always)]
+    pub fn clear_accessed(self) -> Self {
+        return self.set_accessed(false);
+    }
+
+    /// @brief 设置当前页表项的Dirty位
+    ///
+    /// Dirty位由MMU在该页表项第一次被写入时自动置位
+    #[must_use]
+    #[inline(always)]
+    pub fn set_dirty(self, value: bool) -> Self {
+        return self.update_flags(Arch::ENTRY_FLAG_DIRTY, value);
+    }
+
+    /// @brief 当前页表项自上次被清除Dirty位以来，是否被写入过
+    #[inline(always)]
+    pub fn dirty(&self) -> bool {
+        return self.has_flag(Arch::ENTRY_FLAG_DIRTY);
+    }
+
+    /// @brief 清除当前页表项的Dirty位
+    #[must_use]
+    #[inline(always)]
+    pub fn clear_dirty(self) -> Self {
+        return self.set_dirty(false);
+    }
+
+    /// @brief 设置当前页表项的写时复制（Copy-on-Write）标志位
+    ///
+    /// 这是一个软件定义的标志位，由架构实现在[`ENTRY_FLAG_COW`]中分配一个MMU不会用到的空闲位。
+    /// 当一个本来可写的页面被标记为COW时，需要同时将其设置为只读，使得写入会触发缺页异常，
+    /// 从而被[`PageMapper::try_cow_fault`]捕获并处理。
+    ///
+    /// [`ENTRY_FLAG_COW`]: MemoryManagementArch::ENTRY_FLAG_COW
+    #[must_use]
+    #[inline(always)]
+    pub fn set_cow(self, value: bool) -> Self {
+        return self.update_flags(Arch::ENTRY_FLAG_COW, value);
+    }
+
+    /// @brief 当前页表项是否是一个写时复制页面
+    #[inline(always)]
+    pub fn cow(&self) -> bool {
+        return self.has_flag(Arch::ENTRY_FLAG_COW);
+    }
+
+    /// @brief 清除当前页表项的写时复制标志位
+    #[must_use]
+    #[inline(always)]
+    pub fn clear_cow(self) -> Self {
+        return self.set_cow(false);
+    }
 }
 
 impl<Arch: MemoryManagementArch> fmt::Debug for PageFlags<Arch> {
@@ -335,6 +440,23 @@ impl<Arch: MemoryManagementArch> fmt::Debug for PageFlags<Arch> {
     }
 }
 
+/// [`PageMapper::dump_mappings`]内部使用，描述一段flags、level都相同的连续映射范围
+struct MappingRange<Arch> {
+    virt_start: VirtAddr,
+    /// 范围的结束虚拟地址（不包含）
+    virt_end: VirtAddr,
+    phys_start: PhysAddr,
+    flags: PageFlags<Arch>,
+    level: usize,
+}
+
+impl<Arch: MemoryManagementArch> MappingRange<Arch> {
+    /// @brief 这段范围的结束物理地址（不包含）
+    fn phys_end(&self) -> PhysAddr {
+        return PhysAddr::new(self.phys_start.data() + (self.virt_end.data() - self.virt_start.data()));
+    }
+}
+
 /// @brief 页表映射器
 #[derive(Hash)]
 pub struct PageMapper<Arch, F> {
@@ -453,6 +575,101 @@ impl<Arch: MemoryManagementArch, F: FrameAllocator> PageMapper<Arch, F> {
                 table.set_entry(i, entry);
                 return Some(PageFlush::new(virt));
             } else {
+                // 如果这个槽位已经是一个大页的叶子节点，它指向的是实际数据而不是下一级页表，
+                // 不能把next_level_table的结果当成页表来继续向下遍历/写入，否则会破坏大页的内容
+                if table.entry(i)?.is_leaf(table.level()) {
+                    kerror!(
+                        "Try to map page {:?} over an existing huge page",
+                        virt
+                    );
+                    return None;
+                }
+
+                let next_table = table.next_level_table(i);
+                if let Some(next_table) = next_table {
+                    table = next_table;
+                } else {
+                    // 分配下一级页表
+                    let frame = self.frame_allocator.allocate_one()?;
+                    // 设置页表项的flags
+                    let flags = Arch::ENTRY_FLAG_READWRITE
+                        | Arch::ENTRY_FLAG_DEFAULT_TABLE
+                        | if virt.kind() == PageTableKind::User {
+                            Arch::ENTRY_FLAG_USER
+                        } else {
+                            0
+                        };
+                    // 把新分配的页表映射到当前页表
+                    table.set_entry(i, PageEntry::new(frame.data() | flags));
+                    // 获取新分配的页表
+                    table = table.next_level_table(i)?;
+                }
+            }
+        }
+    }
+
+    /// @brief 以大页（huge page）的方式，将一个物理页帧映射到指定的虚拟地址
+    ///
+    /// 与`map_phys`总是把叶子页表项写到第0级页表不同，这个函数把叶子页表项写到指定的中间level，
+    /// 从而让一个页表项覆盖`PAGE_SIZE << (level * PAGE_ENTRY_SHIFT)`这么大的一段连续地址空间，
+    /// 减少大块连续映射（如大块物理内存、巨页）所需要的页表项数量和页表级数。
+    ///
+    /// @param virt 要映射的虚拟地址，必须按该level的大页大小对齐
+    /// @param phys 要映射到的物理地址，必须按该level的大页大小对齐
+    /// @param flags 页表项的标志位
+    /// @param level 叶子页表项所在的level（0表示普通4K页，请直接使用`map_phys`）
+    pub unsafe fn map_huge_phys(
+        &mut self,
+        virt: VirtAddr,
+        phys: PhysAddr,
+        flags: PageFlags<Arch>,
+        level: usize,
+    ) -> Option<PageFlush<Arch>> {
+        // 大页所覆盖的地址空间大小
+        let huge_page_size = Arch::PAGE_SIZE << (level * Arch::PAGE_ENTRY_SHIFT);
+
+        // 验证虚拟地址和物理地址是否按大页大小对齐
+        if virt.data() % huge_page_size != 0 || phys.data() % huge_page_size != 0 {
+            kerror!(
+                "Try to map huge page (level={}) with unaligned addr: virt={:?}, phys={:?}",
+                level,
+                virt,
+                phys
+            );
+            return None;
+        }
+
+        // 创建页表项
+        let entry = PageEntry::new(phys.data() | flags.data());
+        let mut table = self.table();
+
+        loop {
+            let i = table.index_of(virt)?;
+            if table.level() == level {
+                if table.entry_mapped(i)? {
+                    // 如果这个槽位上的页表项，已经指向了一个下一级页表（无论其中是否还有映射），拒绝用大页覆盖它
+                    if !table.entry(i)?.is_leaf(table.level()) {
+                        kerror!(
+                            "Try to map huge page {:?} over an existing page table",
+                            virt
+                        );
+                        return None;
+                    }
+                    panic!("Page {:?} already mapped", virt);
+                }
+                table.set_entry(i, entry);
+                return Some(PageFlush::new(virt));
+            } else {
+                // 如果这个槽位已经是一个（更粗粒度的）大页的叶子节点，它指向的是实际数据而不是
+                // 下一级页表，不能继续把它当成页表向下遍历
+                if table.entry(i)?.is_leaf(table.level()) {
+                    kerror!(
+                        "Try to map huge page {:?} over an existing huge page",
+                        virt
+                    );
+                    return None;
+                }
+
                 let next_table = table.next_level_table(i);
                 if let Some(next_table) = next_table {
                     table = next_table;
@@ -521,6 +738,132 @@ impl<Arch: MemoryManagementArch, F: FrameAllocator> PageMapper<Arch, F> {
         return Some((paddr, flags));
     }
 
+    /// @brief 深度优先遍历当前页表中所有存在的叶子页表项（包括大页），对每一个都调用一次f
+    ///
+    /// 与[`translate`]一次只能查询一个地址不同，这个函数用于一次性获取整个地址空间的映射情况，
+    /// 典型用法是[`dump_mappings`]这样的调试工具。
+    ///
+    /// [`translate`]: PageMapper::translate
+    /// [`dump_mappings`]: PageMapper::dump_mappings
+    ///
+    /// @param f 对每一个存在的叶子页表项，调用一次这个回调函数：虚拟地址、物理地址、flags、
+    /// 这个叶子页表项所在的level（0表示4K页，大于0表示大页）
+    pub fn walk(&self, mut f: impl FnMut(VirtAddr, PhysAddr, PageFlags<Arch>, usize)) {
+        let table = self.table();
+        Self::walk_inner(&table, &mut f);
+    }
+
+    /// @brief `walk`的递归实现，深度优先遍历table这棵子树下的所有叶子页表项
+    fn walk_inner(
+        table: &PageTable<Arch>,
+        f: &mut impl FnMut(VirtAddr, PhysAddr, PageFlags<Arch>, usize),
+    ) {
+        for i in 0..Arch::PAGE_ENTRY_NUM {
+            let entry = unsafe { table.entry(i) }.expect("invalid page table index");
+            if !entry.present() {
+                continue;
+            }
+
+            if entry.is_leaf(table.level()) {
+                if let Ok(paddr) = entry.address() {
+                    let virt = table.entry_base(i).expect("invalid page table index");
+                    f(virt, paddr, entry.flags(), table.level());
+                }
+            } else if let Some(subtable) = unsafe { table.next_level_table(i) } {
+                Self::walk_inner(&subtable, f);
+            }
+        }
+    }
+
+    /// @brief 以“system.map”风格，打印当前地址空间的全部映射情况，用于调试映射问题
+    ///
+    /// 虚拟地址和物理地址都连续、flags和level都相同的叶子页表项会被合并成一个连续的范围一起打印，而不是逐页打印，
+    /// 这样才能在映射了大段连续内存（如物理内存线性映射区）时，仍然保持输出可读。
+    pub fn dump_mappings(&self) {
+        let mut ranges: Vec<MappingRange<Arch>> = Vec::new();
+
+        self.walk(|virt, phys, flags, level| {
+            let page_size = Arch::PAGE_SIZE << (level * Arch::PAGE_ENTRY_SHIFT);
+
+            let extends_last = if let Some(last) = ranges.last() {
+                last.level == level
+                    && last.flags.data() == flags.data()
+                    && last.virt_end == virt
+                    && last.phys_end() == phys
+            } else {
+                false
+            };
+
+            if extends_last {
+                let last = ranges.last_mut().unwrap();
+                last.virt_end = VirtAddr::new(virt.data() + page_size);
+            } else {
+                ranges.push(MappingRange {
+                    virt_start: virt,
+                    virt_end: VirtAddr::new(virt.data() + page_size),
+                    phys_start: phys,
+                    flags,
+                    level,
+                });
+            }
+        });
+
+        for range in ranges {
+            let page_size = Arch::PAGE_SIZE << (range.level * Arch::PAGE_ENTRY_SHIFT);
+            kerror!(
+                "{:?}..{:?} -> {:?} flags={:?} page_size={:#x}",
+                range.virt_start,
+                range.virt_end,
+                range.phys_start,
+                range.flags,
+                page_size
+            );
+        }
+    }
+
+    /// @brief 遍历当前页表中所有存在的叶子页表项（包括大页），对每一个都调用一次f
+    ///
+    /// 典型用法是页面置换算法的“扫描”阶段：f读取并清除每个叶子页表项的Accessed/Dirty位，
+    /// 从而构建出“哪些页面最近被访问/写过”的信息，而不需要每个子系统自己实现一遍页表遍历。
+    ///
+    /// 请注意，需要在扫描结束后，对返回的刷新器调用flush方法，才能使本次扫描中，对页表项的
+    /// 修改（例如清除Accessed位）在TLB中生效。
+    ///
+    /// @param f 对每一个存在的叶子页表项，调用一次这个回调函数
+    ///
+    /// @return 用于刷新整个页表的刷新器
+    pub unsafe fn sweep_accessed(
+        &mut self,
+        mut f: impl FnMut(VirtAddr, &mut PageEntry<Arch>),
+    ) -> PageFlushAll<Arch> {
+        let mut table = self.table();
+        Self::sweep_accessed_inner(&mut table, &mut f);
+        return PageFlushAll::new();
+    }
+
+    /// @brief `sweep_accessed`的递归实现，深度优先遍历table这棵子树下的所有叶子页表项
+    fn sweep_accessed_inner(
+        table: &mut PageTable<Arch>,
+        f: &mut impl FnMut(VirtAddr, &mut PageEntry<Arch>),
+    ) {
+        for i in 0..Arch::PAGE_ENTRY_NUM {
+            let mut entry = unsafe { table.entry(i) }.expect("invalid page table index");
+            if !entry.present() {
+                continue;
+            }
+
+            if entry.is_leaf(table.level()) {
+                let virt = table
+                    .entry_base(i)
+                    .expect("invalid page table index");
+                f(virt, &mut entry);
+                unsafe { table.set_entry(i, entry) };
+            } else if let Some(mut subtable) = unsafe { table.next_level_table(i) } {
+                Self::sweep_accessed_inner(&mut subtable, f);
+            }
+        }
+    }
+
     /// @brief 取消虚拟地址的映射，释放页面，并返回页表项刷新器
     ///
     /// 请注意，需要在取消映射后，调用刷新器的flush方法，才能使修改生效
@@ -530,22 +873,23 @@ impl<Arch: MemoryManagementArch, F: FrameAllocator> PageMapper<Arch, F> {
     ///
     /// @return 如果取消成功，返回刷新器，否则返回None
     pub unsafe fn unmap(&mut self, virt: VirtAddr, unmap_parents: bool) -> Option<PageFlush<Arch>> {
-        let (paddr, _, flusher) = self.unmap_phys(virt, unmap_parents)?;
-        self.frame_allocator.free_one(paddr);
+        let (paddr, _, level, flusher) = self.unmap_phys(virt, unmap_parents)?;
+        free_unmapped_leaf::<Arch>(self.allocator_mut(), paddr, level);
         return Some(flusher);
     }
 
-    /// @brief 取消虚拟地址的映射，并返回物理地址和页表项的flags
+    /// @brief 取消虚拟地址的映射，并返回物理地址、页表项的flags和所在的level
     ///
     /// @param vaddr 虚拟地址
     /// @param unmap_parents 是否在父页表内，取消空闲子页表的映射
     ///
-    /// @return 如果取消成功，返回物理地址和页表项的flags，否则返回None
+    /// @return 如果取消成功，返回物理地址、页表项的flags、被取消映射的页表项所在的level
+    /// （0表示4K页，大于0表示大页），否则返回None
     pub unsafe fn unmap_phys(
         &mut self,
         virt: VirtAddr,
         unmap_parents: bool,
-    ) -> Option<(PhysAddr, PageFlags<Arch>, PageFlush<Arch>)> {
+    ) -> Option<(PhysAddr, PageFlags<Arch>, usize, PageFlush<Arch>)> {
         if !virt.check_aligned(Arch::PAGE_SIZE) {
             kerror!("Try to unmap unaligned page: virt={:?}", virt);
             return None;
@@ -553,7 +897,159 @@ impl<Arch: MemoryManagementArch, F: FrameAllocator> PageMapper<Arch, F> {
 
         let mut table = self.table();
         return unmap_phys_inner(virt, &mut table, unmap_parents, self.allocator_mut())
-            .map(|(paddr, flags)| (paddr, flags, PageFlush::<Arch>::new(virt)));
+            .map(|(paddr, flags, level)| (paddr, flags, level, PageFlush::<Arch>::new(virt)));
+    }
+
+    /// @brief 一次性映射一段连续的物理地址到一段连续的虚拟地址，在对齐条件允许的情况下，
+    /// 自动提升为能容纳下的最大的大页，边界处不足一个大页的部分则退化为4K页
+    ///
+    /// 与循环调用`map_phys`相比，这个函数只返回一个[`PageFlushRange`]，调用者只需要在全部
+    /// 映射完成后，统一刷新一次这段地址范围，而不需要逐页刷新或者刷新整个页表
+    ///
+    /// @param virt 虚拟地址区间的起始地址，必须按4K页对齐
+    /// @param phys 物理地址区间的起始地址，必须按4K页对齐
+    /// @param count 要映射的4K页的数量
+    /// @param flags 页表项的flags
+    ///
+    /// @return 如果映射成功，返回覆盖这段地址区间的刷新器，否则返回None
+    pub unsafe fn map_range(
+        &mut self,
+        virt: VirtAddr,
+        phys: PhysAddr,
+        count: usize,
+        flags: PageFlags<Arch>,
+    ) -> Option<PageFlushRange<Arch>> {
+        let total_size = count * Arch::PAGE_SIZE;
+        let flags_data = flags.data();
+        let mut offset = 0usize;
+
+        while offset < total_size {
+            let cur_virt = VirtAddr::new(virt.data() + offset);
+            let cur_phys = PhysAddr::new(phys.data() + offset);
+            let remaining = total_size - offset;
+
+            // 从最大的大页级别开始尝试，只有地址对齐、且剩余长度足够覆盖一个大页时，才会被提升为大页
+            let mut promoted_size = None;
+            for level in (1..Arch::PAGE_LEVELS).rev() {
+                let huge_page_size = Arch::PAGE_SIZE << (level * Arch::PAGE_ENTRY_SHIFT);
+                if remaining >= huge_page_size
+                    && cur_virt.check_aligned(huge_page_size)
+                    && cur_phys.check_aligned(huge_page_size)
+                {
+                    self.map_huge_phys(cur_virt, cur_phys, PageFlags::new(flags_data), level)?
+                        .ignore();
+                    promoted_size = Some(huge_page_size);
+                    break;
+                }
+            }
+
+            offset += match promoted_size {
+                Some(size) => size,
+                None => {
+                    self.map_phys(cur_virt, cur_phys, PageFlags::new(flags_data))?
+                        .ignore();
+                    Arch::PAGE_SIZE
+                }
+            };
+        }
+
+        return Some(PageFlushRange::new(
+            virt,
+            VirtAddr::new(virt.data() + total_size),
+        ));
+    }
+
+    /// @brief 取消一段连续的虚拟地址的映射，释放对应的页面
+    ///
+    /// @param virt 虚拟地址区间的起始地址，必须按4K页对齐
+    /// @param count 要取消映射的4K页的数量
+    /// @param unmap_parents 是否在父页表内，取消空闲子页表的映射
+    ///
+    /// 这个函数要求`[virt, virt + count * PAGE_SIZE)`这段区间此前是以与该区间对齐的粒度建立的
+    /// 映射：区间内遇到的每一个大页叶子节点，其完整范围都必须落在这段区间之内。如果这段区间的
+    /// 某一部分原本是以更粗的粒度（比如跨越区间边界的大页）映射的，直接取消映射会误将这个大页
+    /// 落在请求范围之外的部分一并释放，因此这里会在释放物理页帧前进行校验并panic（此时该叶子
+    /// 页表项已经被清除）。
+    ///
+    /// @return 如果取消成功，返回覆盖这段地址区间的刷新器，否则返回None
+    pub unsafe fn unmap_range(
+        &mut self,
+        virt: VirtAddr,
+        count: usize,
+        unmap_parents: bool,
+    ) -> Option<PageFlushRange<Arch>> {
+        let total_size = count * Arch::PAGE_SIZE;
+        let mut offset = 0usize;
+
+        while offset < total_size {
+            let cur_virt = VirtAddr::new(virt.data() + offset);
+            // 如果这个虚拟地址原本是以大页的方式映射的，这一次调用会直接取消整个大页的映射
+            let (paddr, _, level, flush) = self.unmap_phys(cur_virt, unmap_parents)?;
+            let leaf_size = Arch::PAGE_SIZE << (level * Arch::PAGE_ENTRY_SHIFT);
+            assert!(
+                cur_virt.check_aligned(leaf_size) && offset + leaf_size <= total_size,
+                "unmap_range: the matched leaf at {:?} (level {}, size {:#x}) is not fully \
+                 contained in the requested unmap range; unmap_range requires the region to \
+                 have been mapped at a granularity matching the requested range",
+                cur_virt,
+                level,
+                leaf_size
+            );
+            free_unmapped_leaf::<Arch>(self.allocator_mut(), paddr, level);
+            flush.ignore();
+            offset += leaf_size;
+        }
+
+        return Some(PageFlushRange::new(
+            virt,
+            VirtAddr::new(virt.data() + total_size),
+        ));
+    }
+
+    /// @brief 处理写时复制（Copy-on-Write）页面的缺页异常
+    ///
+    /// 在通过[`clone_cow`]复制地址空间后，父子进程共享的用户态页面都会被标记为只读+COW。
+    /// 当其中一方尝试写入这样的页面时，会触发缺页异常；这个函数应当在异常处理流程中被调用：
+    /// 如果virt确实对应一个COW页面，就为当前地址空间分配一份私有的物理页拷贝，把原来的内容复制
+    /// 过去，并将页表项指向这份私有拷贝、重新标记为可写；如果不是COW页面，则直接返回None，
+    /// 调用者需要按照其他方式处理这次缺页异常（例如视为非法访问）。
+    ///
+    /// [`clone_cow`]: PageMapper::clone_cow
+    ///
+    /// @param virt 发生缺页异常的虚拟地址
+    ///
+    /// @return 如果处理了一次COW缺页，返回用于刷新该页的刷新器；否则返回None
+    pub unsafe fn try_cow_fault(&mut self, virt: VirtAddr) -> Option<PageFlush<Arch>> {
+        let (old_paddr, flags) = self
+            .visit(virt, |table, i| {
+                let entry = table.entry(i)?;
+                Some((entry.address().ok()?, entry.flags()))
+            })
+            .flatten()?;
+
+        if !flags.cow() {
+            return None;
+        }
+
+        let new_paddr = self.frame_allocator.allocate_one()?;
+        // old_paddr/new_paddr必须都能通过direct-map窗口访问到，否则没有办法把原始内容拷贝过去；
+        // 这种情况下不能假装拷贝成功、继续把new_paddr装订为可写页，那样会让进程写入一个未初始化的页面。
+        let old_virt = Arch::phys_2_virt(old_paddr)?;
+        let new_virt = Arch::phys_2_virt(new_paddr)?;
+        core::ptr::copy_nonoverlapping(
+            old_virt.data() as *const u8,
+            new_virt.data() as *mut u8,
+            Arch::PAGE_SIZE,
+        );
+
+        let new_flags = flags.set_write(true).clear_cow();
+        self.visit(virt, |table, i| {
+            table.set_entry(i, PageEntry::new(new_paddr.data() | new_flags.data()))
+        });
+
+        frame_drop_ref(old_paddr);
+
+        return Some(PageFlush::new(virt));
     }
 
     /// @brief 在页表中，访问虚拟地址对应的页表项，并调用传入的函数F
@@ -566,7 +1062,8 @@ impl<Arch: MemoryManagementArch, F: FrameAllocator> PageMapper<Arch, F> {
         unsafe {
             loop {
                 let i = table.index_of(virt)?;
-                if table.level() == 0 {
+                // 第0级页表项总是叶子节点；大页的叶子节点也可能出现在更高的level中
+                if table.level() == 0 || table.entry(i)?.is_leaf(table.level()) {
                     return Some(f(&mut table, i));
                 } else {
                     table = table.next_level_table(i)?;
@@ -576,28 +1073,131 @@ impl<Arch: MemoryManagementArch, F: FrameAllocator> PageMapper<Arch, F> {
     }
 }
 
-/// @brief 取消页面映射，返回被取消映射的页表项的：【物理地址】和【flags】
+impl<Arch: MemoryManagementArch, F: FrameAllocator + Clone> PageMapper<Arch, F> {
+    /// @brief 以写时复制（Copy-on-Write）的方式，复制一份当前地址空间，典型地用于实现fork()
+    ///
+    /// 内核态的映射（即[`VirtAddr::kind`]为[`PageTableKind::Kernel`]的部分）在所有地址空间间
+    /// 共享，会被原样复制，不受COW影响；用户态的映射则会在父子双方都被标记为只读+COW，
+    /// 共享同一份物理页帧，直到其中一方尝试写入、触发[`try_cow_fault`]为止。
+    ///
+    /// 请注意，调用者需要自行刷新当前页表（例如通过[`PageFlushAll`]），因为本函数修改了
+    /// 当前地址空间内，原本可写的用户态页表项的标志位。
+    ///
+    /// [`try_cow_fault`]: PageMapper::try_cow_fault
+    ///
+    /// @return 如果复制成功，返回新地址空间的页面映射器；否则（例如物理内存不足）返回None
+    pub unsafe fn clone_cow(&mut self) -> Option<Self> {
+        let mut dst_allocator = self.frame_allocator.clone();
+        let dst_table_paddr = dst_allocator.allocate_one()?;
+
+        let mut src_table = self.table();
+        let dst_table = PageTable::<Arch>::new(VirtAddr::new(0), dst_table_paddr, src_table.level());
+        dst_table.zero();
+
+        Self::clone_cow_inner(&mut src_table, &dst_table, &mut dst_allocator)?;
+
+        return Some(Self::new(self.table_kind, dst_table_paddr, dst_allocator));
+    }
+
+    /// @brief `clone_cow`的递归实现，深度优先遍历src这棵子树，将其克隆到dst对应的子树中
+    fn clone_cow_inner(
+        src: &mut PageTable<Arch>,
+        dst: &PageTable<Arch>,
+        dst_allocator: &mut impl FrameAllocator,
+    ) -> Option<()> {
+        for i in 0..Arch::PAGE_ENTRY_NUM {
+            let entry = unsafe { src.entry(i) }.expect("invalid page table index");
+            if !entry.present() {
+                continue;
+            }
+
+            let virt = src.entry_base(i).expect("invalid page table index");
+
+            if entry.is_leaf(src.level()) {
+                if virt.kind() == PageTableKind::Kernel {
+                    // 内核态的叶子页面在所有地址空间间共享，原样复制，不做COW处理
+                    unsafe { dst.set_entry(i, entry) };
+                } else {
+                    // 用户态的叶子页面：父子各持有一份只读引用，共享同一个物理页帧
+                    //
+                    // 这里的cow位要记录“这个页原本（被fork前）是否可写”，而不能只看当前PTE的
+                    // write位：如果这个页已经是上一次fork留下的COW页（write=false, cow=true），
+                    // 再次fork时write()会是false，如果只用write()来决定cow，会把cow位错误地
+                    // 清掉，导致祖先进程这份仍然共享的页面此后不再被try_cow_fault处理。
+                    let was_writable = entry.flags().write() || entry.flags().cow();
+                    let shared_flags = entry.flags().set_write(false).set_cow(was_writable);
+                    let shared_data = entry.address().ok()?.data() | shared_flags.data();
+                    unsafe { src.set_entry(i, PageEntry::new(shared_data)) };
+                    unsafe { dst.set_entry(i, PageEntry::new(shared_data)) };
+                    frame_add_ref(entry.address().ok()?);
+                }
+            } else if virt.kind() == PageTableKind::Kernel {
+                // 内核态的页表在所有地址空间间共享，不需要为新地址空间重新分配
+                unsafe { dst.set_entry(i, entry) };
+            } else {
+                // 用户态的页表：为新地址空间分配一份私有的页表，递归克隆其中的内容
+                let new_subtable_paddr = unsafe { dst_allocator.allocate_one() }?;
+                let table_flags = entry.data() & Arch::ENTRY_FLAGS_MASK;
+                unsafe { dst.set_entry(i, PageEntry::new(new_subtable_paddr.data() | table_flags)) };
+
+                let mut src_subtable = unsafe { src.next_level_table(i) }?;
+                let dst_subtable = unsafe { dst.next_level_table(i) }?;
+                unsafe { dst_subtable.zero() };
+                Self::clone_cow_inner(&mut src_subtable, &dst_subtable, dst_allocator)?;
+            }
+        }
+
+        return Some(());
+    }
+}
+
+/// @brief 归还一个刚被取消映射的叶子页表项所占据的物理页帧
+///
+/// 4K页（level为0）可能是`clone_cow`产生的写时复制共享页，必须经过[`frame_drop_ref`]查询
+/// 引用计数表，只有确认不再被其它地址空间共享时才真正归还给全局页帧分配器，否则仍被共享的
+/// 一方会留下指向已经被重新分配出去的物理内存的悬挂映射。大页叶子节点目前不会经过写时复制
+/// 的引用计数（`clone_cow_inner`对大页只按基准页帧增加一次引用计数，不会覆盖大页实际占据的
+/// 所有页帧），因此按其覆盖的页帧数整体归还给分配器，而不是只归还一个页帧。
+///
+/// @param allocator 页面分配器
+/// @param paddr 被取消映射的叶子页表项指向的物理地址
+/// @param level 被取消映射的叶子页表项所在的level（0表示4K页，大于0表示大页）
+fn free_unmapped_leaf<Arch: MemoryManagementArch>(
+    allocator: &mut impl FrameAllocator,
+    paddr: PhysAddr,
+    level: usize,
+) {
+    if level == 0 {
+        frame_drop_ref(paddr);
+    } else {
+        let leaf_size = Arch::PAGE_SIZE << (level * Arch::PAGE_ENTRY_SHIFT);
+        unsafe { allocator.free(paddr, PageFrameCount::new(leaf_size / Arch::PAGE_SIZE)) };
+    }
+}
+
+/// @brief 取消页面映射，返回被取消映射的页表项的：【物理地址】、【flags】和【level】
 ///
 /// @param vaddr 虚拟地址
 /// @param table 页表
 /// @param unmap_parents 是否在父页表内，取消空闲子页表的映射
 /// @param allocator 页面分配器（如果页表从这个分配器分配，那么在取消映射时，也需要归还到这个分配器内）
 ///
-/// @return 如果取消成功，返回被取消映射的页表项的：【物理地址】和【flags】，否则返回None
+/// @return 如果取消成功，返回被取消映射的页表项的：【物理地址】、【flags】和其所在的level
+/// （0表示4K页，大于0表示大页），否则返回None
 unsafe fn unmap_phys_inner<Arch: MemoryManagementArch>(
     vaddr: VirtAddr,
     table: &mut PageTable<Arch>,
     unmap_parents: bool,
     allocator: &mut impl FrameAllocator,
-) -> Option<(PhysAddr, PageFlags<Arch>)> {
+) -> Option<(PhysAddr, PageFlags<Arch>, usize)> {
     // 获取页表项的索引
     let i = table.index_of(vaddr)?;
+    let entry = table.entry(i)?;
 
-    // 如果当前是最后一级页表，直接取消页面映射
-    if table.level() == 0 {
-        let entry = table.entry(i)?;
+    // 如果当前是最后一级页表，或者当前页表项是一个大页的叶子节点，直接取消映射
+    if table.level() == 0 || entry.is_leaf(table.level()) {
         table.set_entry(i, PageEntry::new(0));
-        return Some((entry.address().ok()?, entry.flags()));
+        return Some((entry.address().ok()?, entry.flags(), table.level()));
     }
 
     let mut subtable = table.next_level_table(i)?;
@@ -696,6 +1296,65 @@ impl<Arch: MemoryManagementArch> Flusher<Arch> for PageFlushAll<Arch> {
     }
 }
 
+/// 超过这个页数的范围刷新，会直接刷新整个TLB，而不是逐页刷新
+///
+/// 逐页发出invalidate指令本身也有开销，当需要刷新的页数足够多时，不如直接刷新整个TLB更划算
+const PAGE_FLUSH_RANGE_THRESHOLD: usize = 16;
+
+/// @brief 用于刷新一段连续虚拟地址区间的刷新器。这个刷新器一经产生，就必须调用flush()方法，
+/// 否则会造成对页表的更改被忽略，这是不安全的
+///
+/// 由[`PageMapper::map_range`]/[`PageMapper::unmap_range`]产生，用于避免在批量映射/取消映射
+/// 一段连续地址时，逐页调用[`PageFlush::flush`]带来的开销，同时又不必像[`PageFlushAll`]一样
+/// 刷新整个地址空间
+#[must_use = "The flusher must call the 'flush()', or the changes to page table will be unsafely ignored."]
+pub struct PageFlushRange<Arch> {
+    /// 这段范围的起始虚拟地址
+    start: VirtAddr,
+    /// 这段范围的结束虚拟地址（不包含）
+    end: VirtAddr,
+    phantom: PhantomData<Arch>,
+}
+
+impl<Arch: MemoryManagementArch> PageFlushRange<Arch> {
+    pub fn new(start: VirtAddr, end: VirtAddr) -> Self {
+        return Self {
+            start,
+            end,
+            phantom: PhantomData,
+        };
+    }
+
+    /// @brief 刷新这段范围内，所有虚拟地址对应的TLB条目
+    ///
+    /// 如果这段范围覆盖的页数不超过[`PAGE_FLUSH_RANGE_THRESHOLD`]，就逐页发出刷新指令，
+    /// 否则直接刷新整个TLB
+    pub fn flush(self) {
+        let page_count = (self.end.data() - self.start.data()) / Arch::PAGE_SIZE;
+        if page_count <= PAGE_FLUSH_RANGE_THRESHOLD {
+            let mut addr = self.start;
+            while addr.data() < self.end.data() {
+                unsafe { Arch::invalidate_page(addr) };
+                addr = VirtAddr::new(addr.data() + Arch::PAGE_SIZE);
+            }
+        } else {
+            unsafe { Arch::invalidate_all() };
+        }
+    }
+
+    /// @brief 忽略掉这个刷新器
+    pub unsafe fn ignore(self) {
+        mem::forget(self);
+    }
+}
+
+impl<Arch: MemoryManagementArch> Flusher<Arch> for PageFlushRange<Arch> {
+    /// 这段范围本身已经记录了需要刷新的地址区间，因此可以直接忽略掉单个页面的刷新器
+    fn consume(&mut self, flush: PageFlush<Arch>) {
+        unsafe { flush.ignore() };
+    }
+}
+
 impl<Arch: MemoryManagementArch, T: Flusher<Arch> + ?Sized> Flusher<Arch> for &mut T {
     /// 允许一个flusher consume掉另一个flusher
     fn consume(&mut self, flush: PageFlush<Arch>) {
@@ -707,6 +1366,79 @@ impl<Arch: MemoryManagementArch> Flusher<Arch> for () {
     fn consume(&mut self, flush: PageFlush<Arch>) {}
 }
 
+/// @brief 描述内核地址空间中，一个需要单独设置访问权限的段（如.text、.rodata、.data/.bss）
+#[derive(Debug, Clone, Copy)]
+pub struct KernelSection {
+    /// 段的起始虚拟地址（无需按页对齐）
+    pub start: VirtAddr,
+    /// 段的结束虚拟地址（不包含，无需按页对齐）
+    pub end: VirtAddr,
+    /// 这个段是否需要可写
+    pub writable: bool,
+    /// 这个段是否需要可执行
+    pub executable: bool,
+}
+
+impl KernelSection {
+    pub fn new(start: VirtAddr, end: VirtAddr, writable: bool, executable: bool) -> Self {
+        return Self {
+            start,
+            end,
+            writable,
+            executable,
+        };
+    }
+}
+
+/// @brief 根据内核各个段的权限需求，重新建立一份内核页表，并将其设置为当前页表
+///
+/// 内核在启动阶段，通常以一整块可读可写可执行的粗粒度恒等映射区域来映射自身，这个函数按照
+/// `sections`中给出的每个段各自实际需要的权限（典型地：`.text`只读+可执行，`.rodata`只读+
+/// 不可执行，`.data`/`.bss`可读写+不可执行），重新建立一份内核页表，恒等映射每个段，最后把
+/// 这份新的页表设置为当前页表。这实现了rCore实验教程中“内核重映射”这一步，使得内核不再以
+/// 可写又可执行的页面运行。
+///
+/// 请注意，调用者需要对返回的刷新器调用`flush()`，才能使TLB中的旧映射失效。
+///
+/// @param sections 内核各个段的起止地址和权限需求（起止地址无需手动按页对齐）
+/// @param allocator 用于分配新页表、新页帧的分配器
+///
+/// @return 如果重映射成功，返回新的页面映射器，以及用于刷新整个页表的刷新器；否则（例如物理
+/// 内存不足）返回None
+pub unsafe fn remap_kernel<F: FrameAllocator>(
+    sections: &[KernelSection],
+    allocator: F,
+) -> Option<(PageMapper<MMArch, F>, PageFlushAll<MMArch>)> {
+    let mut mapper = PageMapper::create(PageTableKind::Kernel, allocator)?;
+    // 新分配的根页表可能残留上一次使用时的数据，必须先清零，否则某些未被sections覆盖的槽位
+    // 会被当成一个携带任意权限、指向随机物理内存的合法页表项
+    mapper.table().zero();
+    let mut flush_all = PageFlushAll::new();
+
+    for section in sections {
+        let start = round_down_to_page_size(section.start.data());
+        let end = round_up_to_page_size(section.end.data());
+
+        let flags_data = PageFlags::<MMArch>::new(MMArch::ENTRY_FLAG_PRESENT | MMArch::ENTRY_FLAG_NO_EXEC)
+            .set_write(section.writable)
+            .set_execute(section.executable)
+            .data();
+
+        let mut addr = start;
+        while addr < end {
+            let virt = VirtAddr::new(addr);
+            // 内核段在启动时以恒等映射的方式被映射，这里保持同样的映射关系
+            let phys = PhysAddr::new(addr);
+            let page_flush = mapper.map_phys(virt, phys, PageFlags::new(flags_data))?;
+            flush_all.consume(page_flush);
+            addr += MMArch::PAGE_SIZE;
+        }
+    }
+
+    mapper.make_current();
+    return Some((mapper, flush_all));
+}
+
 /// # 把一个地址向下对齐到页大小
 pub fn round_down_to_page_size(addr: usize) -> usize {
     addr & !(MMArch::PAGE_SIZE - 1)